@@ -3,15 +3,65 @@ use clap::{arg, Command};
 use regex::Regex;
 use rusqlite::{Connection, Result};
 use rust_stemmers::{Algorithm, Stemmer};
+use serde::Deserialize;
 use std::error::Error;
 use std::{env, io};
 use uuid::Uuid;
 
+mod snip;
+use crate::snip::attachment::{ensure_content_type_column, text_attachments_for_snip};
+
 struct Snip {
     uuid: String,
     name: String,
     text: String,
     timestamp: DateTime<FixedOffset>,
+    lang: Option<String>,
+}
+
+struct SearchResult {
+    uuid: String,
+    name: String,
+    score: f64,
+}
+
+// BM25 tuning parameters
+const BM25_K1: f64 = 1.2;
+const BM25_B: f64 = 0.75;
+
+// per-edit score multiplier applied to fuzzy matches so exact hits rank first
+const FUZZY_PENALTY: f64 = 0.5;
+
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+struct Config {
+    db_path: Option<String>,
+    data_dir: Option<String>,
+    lang: String,
+    bm25_k1: f64,
+    bm25_b: f64,
+    fuzzy_distance: u32,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            db_path: None,
+            data_dir: None,
+            lang: "english".to_string(),
+            bm25_k1: BM25_K1,
+            bm25_b: BM25_B,
+            fuzzy_distance: 0,
+        }
+    }
+}
+
+impl Config {
+    fn from_file(path: &str) -> Result<Config, Box<dyn Error>> {
+        let contents = std::fs::read_to_string(path)?;
+        let config: Config = toml::from_str(&contents)?;
+        Ok(config)
+    }
 }
 
 fn main() -> Result<(), Box<dyn Error>> {
@@ -19,6 +69,7 @@ fn main() -> Result<(), Box<dyn Error>> {
         .bin_name("snip-rs")
         .arg_required_else_help(true)
         .subcommand_required(true)
+        .arg(arg!(--config <path> "Path to a TOML configuration file").required(false).global(true))
         .subcommand(clap::command!("ls").about("List all snips"))
         .subcommand(
             clap::command!("split")
@@ -30,20 +81,60 @@ fn main() -> Result<(), Box<dyn Error>> {
             Command::new("stem")
                 .about("Stem word from stdin")
                 .arg(arg!(<word> "The word to stem"))
+                .arg(arg!(--lang <language> "Stemmer language to use").required(false))
+                .arg_required_else_help(true),
+        )
+        .subcommand(clap::command!("get").about("Print first snip in database"))
+        .subcommand(
+            Command::new("search")
+                .about("Search snips using the full-text index")
+                .arg(arg!(<terms> ... "The terms to search for"))
+                .arg(arg!(--fuzzy "Expand every term with typo-tolerant matches").required(false))
                 .arg_required_else_help(true),
         )
-        .subcommand(clap::command!("get").about("Print first snip in database"));
+        .subcommand(clap::command!("reindex").about("Rebuild the full-text index"));
 
     let matches = cmd.get_matches();
 
     let db_file_default = ".snip.sqlite3".to_string();
-    let home_dir = match env::var("HOME") {
+
+    // resolve the configuration file. An explicit --config must exist and parse,
+    // so its errors are surfaced; the default location falls back silently.
+    let config = match matches.get_one::<String>("config") {
+        Some(path) => Config::from_file(path)?,
+        None => {
+            let default_path = match env::var("HOME") {
+                Ok(home) => format!("{}/.config/snip-rs/config.toml", home),
+                Err(_) => "config.toml".to_string(),
+            };
+            Config::from_file(&default_path).unwrap_or_default()
+        }
+    };
+
+    // resolve the database path with precedence: env var > config file > built-in default
+    let db_path = match env::var("SNIP_DB") {
         Ok(v) => v,
-        Err(e) => panic!("{}", e),
+        Err(_) => config
+            .db_path
+            .clone()
+            .or_else(|| {
+                // fall back to a file inside the configured data directory
+                config.data_dir.as_ref().map(|dir| format!("{}/{}", dir, db_file_default))
+            })
+            .unwrap_or_else(|| match env::var("HOME") {
+                Ok(home) => format!("{}/{}", home, db_file_default),
+                Err(_) => db_file_default.clone(),
+            }),
     };
-    let db_path = env::var("SNIP_DB").unwrap_or(format!("{}/{}", home_dir, db_file_default));
     let conn = Connection::open(db_path)?;
 
+    // make sure columns added by later features are present on older databases
+    ensure_snip_lang_column(&conn);
+    ensure_content_type_column(&conn);
+
+    // default stemmer language, used when a snip or query specifies none
+    let default_algo = algorithm_from_lang(&config.lang);
+
     // process all subcommands as in: https://docs.rs/clap/latest/clap/_derive/_cookbook/git/index.html
     match matches.subcommand() {
         Some(("get", _)) => {
@@ -67,7 +158,40 @@ fn main() -> Result<(), Box<dyn Error>> {
                 Some(v) => v.to_owned(),
                 None => read_data_from_stdin()?,
             };
-            println!("{} -> {}", term, stem_something(&term));
+            let algo = match sub_matches.get_one::<String>("lang") {
+                Some(v) => algorithm_from_lang(v),
+                None => default_algo,
+            };
+            println!("{} -> {}", term, stem_something(&term, algo));
+        }
+        Some(("reindex", _)) => {
+            let count = match reindex(&conn, default_algo) {
+                Ok(v) => v,
+                Err(e) => panic!("{}", e),
+            };
+            println!("indexed {} snips", count);
+        }
+        Some(("search", sub_matches)) => {
+            let terms: Vec<String> = sub_matches
+                .get_many::<String>("terms")
+                .unwrap_or_default()
+                .map(|s| s.to_owned())
+                .collect();
+            let query = terms.join(" ");
+            let fuzzy = sub_matches.get_flag("fuzzy");
+            let max_fuzzy = if config.fuzzy_distance > 0 {
+                Some(config.fuzzy_distance as usize)
+            } else {
+                None
+            };
+            let results = match search_snips(&conn, &query, fuzzy, config.bm25_k1, config.bm25_b, max_fuzzy, default_algo) {
+                Ok(v) => v,
+                Err(e) => panic!("{}", e),
+            };
+            for r in results {
+                let id = Uuid::parse_str(&r.uuid)?;
+                println!("{:.4} {} {}", r.score, split_uuid(id)[0], r.name);
+            }
         }
         Some(("split", sub_matches)) => {
             let input = match sub_matches.get_one::<String>("string") {
@@ -132,7 +256,7 @@ fn strip_punctuation(s: &str) -> &str{
 }
 
 fn get_first_snip(conn: &Connection) -> Result<Snip, Box<dyn Error>> {
-    let mut stmt = match conn.prepare("SELECT uuid, name, timestamp, data FROM snip LIMIT 1") {
+    let mut stmt = match conn.prepare("SELECT uuid, name, timestamp, data, lang FROM snip LIMIT 1") {
         Ok(v) => v,
         Err(e) => return Err(Box::new(e)),
     };
@@ -150,6 +274,7 @@ fn get_first_snip(conn: &Connection) -> Result<Snip, Box<dyn Error>> {
             name: row.get(1)?,
             timestamp: ts_parsed,
             text: row.get(3)?,
+            lang: row.get(4)?,
         })
     })?;
 
@@ -164,7 +289,7 @@ fn get_first_snip(conn: &Connection) -> Result<Snip, Box<dyn Error>> {
 }
 
 fn list_snips(conn: &Connection) -> Result<(), Box<dyn Error>> {
-    let mut stmt = match conn.prepare("SELECT uuid, name, timestamp, data from snip") {
+    let mut stmt = match conn.prepare("SELECT uuid, name, timestamp, data, lang from snip") {
         Ok(v) => v,
         Err(e) => panic!("{}", e),
     };
@@ -182,6 +307,7 @@ fn list_snips(conn: &Connection) -> Result<(), Box<dyn Error>> {
             name: row.get(1)?,
             timestamp: ts_parsed,
             text: row.get(3)?,
+            lang: row.get(4)?,
         })
     })?;
 
@@ -195,11 +321,314 @@ fn list_snips(conn: &Connection) -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
-fn stem_something(s: &str) -> String {
-    let stemmer = Stemmer::create(Algorithm::English);
+fn get_all_snips(conn: &Connection) -> Result<Vec<Snip>, Box<dyn Error>> {
+    let mut stmt = match conn.prepare("SELECT uuid, name, timestamp, data, lang from snip") {
+        Ok(v) => v,
+        Err(e) => panic!("{}", e),
+    };
+
+    let query_iter = stmt.query_map([], |row| {
+        let ts: String = row.get(2)?;
+        let ts_parsed = match DateTime::parse_from_rfc3339(ts.as_str()) {
+            Ok(v) => v,
+            Err(e) => panic!("{}", e),
+        };
+
+        Ok(Snip {
+            uuid: row.get(0)?,
+            name: row.get(1)?,
+            timestamp: ts_parsed,
+            text: row.get(3)?,
+            lang: row.get(4)?,
+        })
+    })?;
+
+    let mut snips: Vec<Snip> = Vec::new();
+    for snip in query_iter {
+        snips.push(snip?);
+    }
+    Ok(snips)
+}
+
+// add the optional per-snip language column to pre-existing databases; the
+// ALTER fails harmlessly once the column is already present
+fn ensure_snip_lang_column(conn: &Connection) {
+    let _ = conn.execute("ALTER TABLE snip ADD COLUMN lang TEXT", []);
+}
+
+fn create_index_tables(conn: &Connection) -> Result<(), Box<dyn Error>> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS snip_index(term TEXT, snip_uuid TEXT, term_freq INTEGER)",
+        [],
+    )?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS snip_index_doc(snip_uuid TEXT, length INTEGER)",
+        [],
+    )?;
+    Ok(())
+}
+
+fn index_snip(conn: &Connection, snip: &Snip, default_algo: Algorithm) -> Result<(), Box<dyn Error>> {
+    create_index_tables(conn)?;
+
+    // stem in the snip's own language when set, otherwise the configured default
+    let algo = match &snip.lang {
+        Some(lang) => algorithm_from_lang(lang),
+        None => default_algo,
+    };
+
+    // accumulate per-term frequencies from the stemmed token stream
+    let mut freqs: std::collections::HashMap<String, u64> = std::collections::HashMap::new();
+    let mut length: u64 = 0;
+    for word in split_words(&snip.text) {
+        if word.is_empty() {
+            continue;
+        }
+        let term = stem_something(word, algo);
+        *freqs.entry(term).or_insert(0) += 1;
+        length += 1;
+    }
+
+    // fold in the text of any Text-typed attachments so their contents are searchable
+    for text in text_attachments_for_snip(conn, &snip.uuid)? {
+        for word in split_words(&text) {
+            if word.is_empty() {
+                continue;
+            }
+            let term = stem_something(word, algo);
+            *freqs.entry(term).or_insert(0) += 1;
+            length += 1;
+        }
+    }
+
+    // clear any stale postings for this document before inserting
+    conn.execute(
+        "DELETE FROM snip_index WHERE snip_uuid = :uuid",
+        &[(":uuid", &snip.uuid)],
+    )?;
+    conn.execute(
+        "DELETE FROM snip_index_doc WHERE snip_uuid = :uuid",
+        &[(":uuid", &snip.uuid)],
+    )?;
+
+    let mut stmt = conn
+        .prepare("INSERT INTO snip_index(term, snip_uuid, term_freq) VALUES(:term, :snip_uuid, :term_freq)")?;
+    for (term, freq) in &freqs {
+        stmt.execute(&[
+            (":term", term),
+            (":snip_uuid", &snip.uuid),
+            (":term_freq", &freq.to_string()),
+        ])?;
+    }
+
+    conn.execute(
+        "INSERT INTO snip_index_doc(snip_uuid, length) VALUES(:snip_uuid, :length)",
+        &[(":snip_uuid", &snip.uuid), (":length", &length.to_string())],
+    )?;
+    Ok(())
+}
+
+fn reindex(conn: &Connection, default_algo: Algorithm) -> Result<usize, Box<dyn Error>> {
+    create_index_tables(conn)?;
+    conn.execute("DELETE FROM snip_index", [])?;
+    conn.execute("DELETE FROM snip_index_doc", [])?;
+
+    let snips = get_all_snips(conn)?;
+    for s in &snips {
+        index_snip(conn, s, default_algo)?;
+    }
+    Ok(snips.len())
+}
+
+fn edit_distance_within(a: &str, b: &str, max: usize) -> Option<usize> {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    if a.len().abs_diff(b.len()) > max {
+        return None;
+    }
+
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    for i in 1..=a.len() {
+        let mut curr = vec![0usize; b.len() + 1];
+        curr[0] = i;
+        let mut row_min = curr[0];
+        for j in 1..=b.len() {
+            let cost = if a[i - 1] == b[j - 1] { 0 } else { 1 };
+            curr[j] = (prev[j] + 1).min(curr[j - 1] + 1).min(prev[j - 1] + cost);
+            if curr[j] < row_min {
+                row_min = curr[j];
+            }
+        }
+        // abandon the candidate as soon as every cell in the row exceeds the cutoff
+        if row_min > max {
+            return None;
+        }
+        prev = curr;
+    }
+
+    let d = prev[b.len()];
+    if d <= max {
+        Some(d)
+    } else {
+        None
+    }
+}
+
+fn search_snips(
+    conn: &Connection,
+    query: &str,
+    fuzzy: bool,
+    k1: f64,
+    b: f64,
+    max_fuzzy: Option<usize>,
+    algo: Algorithm,
+) -> Result<Vec<SearchResult>, Box<dyn Error>> {
+    // Documents are stemmed per-snip language, so a single algorithm cannot reach
+    // every posting. Stem each query token with the configured default plus every
+    // language present in the collection, keeping the distinct set of stems.
+    let mut algos: Vec<Algorithm> = vec![algo];
+    let mut stmt = conn.prepare("SELECT DISTINCT lang FROM snip WHERE lang IS NOT NULL")?;
+    let langs = stmt.query_map([], |row| row.get::<_, String>(0))?;
+    for lang in langs {
+        algos.push(algorithm_from_lang(&lang?));
+    }
+
+    let mut terms: Vec<String> = Vec::new();
+    for word in split_words(query).into_iter().filter(|w| !w.is_empty()) {
+        for a in &algos {
+            let term = stem_something(word, *a);
+            if !terms.contains(&term) {
+                terms.push(term);
+            }
+        }
+    }
+
+    // collection statistics
+    let n_docs: i64 = conn.query_row("SELECT COUNT(*) FROM snip_index_doc", [], |row| row.get(0))?;
+    if n_docs == 0 {
+        return Ok(Vec::new());
+    }
+    let total_len: i64 = conn
+        .query_row("SELECT COALESCE(SUM(length), 0) FROM snip_index_doc", [], |row| row.get(0))?;
+    let avgdl = total_len as f64 / n_docs as f64;
+    let n = n_docs as f64;
+
+    let mut scores: std::collections::HashMap<String, f64> = std::collections::HashMap::new();
+    for term in &terms {
+        // number of documents containing the exact stemmed term
+        let exact_n_t: i64 = conn.query_row(
+            "SELECT COUNT(DISTINCT snip_uuid) FROM snip_index WHERE term = :term",
+            &[(":term", term)],
+            |row| row.get(0),
+        )?;
+
+        // build the set of index terms that satisfy this query token, each
+        // carrying the edit distance that matched it (0 for an exact hit)
+        let mut candidates: Vec<(String, usize)> = Vec::new();
+        if exact_n_t > 0 {
+            candidates.push((term.clone(), 0));
+        }
+        if fuzzy || exact_n_t == 0 {
+            let allowed = match max_fuzzy {
+                Some(v) => v,
+                None => {
+                    if term.chars().count() <= 5 {
+                        1
+                    } else {
+                        2
+                    }
+                }
+            };
+            let mut stmt = conn.prepare("SELECT DISTINCT term FROM snip_index")?;
+            let rows = stmt.query_map([], |row| row.get::<_, String>(0))?;
+            for candidate in rows {
+                let candidate = candidate?;
+                if candidate == *term {
+                    continue;
+                }
+                if let Some(d) = edit_distance_within(term, &candidate, allowed) {
+                    if d > 0 {
+                        candidates.push((candidate, d));
+                    }
+                }
+            }
+        }
+
+        for (matched, edits) in &candidates {
+            let n_t: i64 = conn.query_row(
+                "SELECT COUNT(DISTINCT snip_uuid) FROM snip_index WHERE term = :term",
+                &[(":term", matched)],
+                |row| row.get(0),
+            )?;
+            if n_t == 0 {
+                continue;
+            }
+            let idf = ((n - n_t as f64 + 0.5) / (n_t as f64 + 0.5) + 1.0).ln();
+            let penalty = FUZZY_PENALTY.powi(*edits as i32);
+
+            let mut stmt = conn.prepare(
+                "SELECT i.snip_uuid, i.term_freq, d.length FROM snip_index i \
+                 JOIN snip_index_doc d ON d.snip_uuid = i.snip_uuid WHERE i.term = :term",
+            )?;
+            let rows = stmt.query_map(&[(":term", matched)], |row| {
+                let uuid: String = row.get(0)?;
+                let freq: i64 = row.get(1)?;
+                let len: i64 = row.get(2)?;
+                Ok((uuid, freq, len))
+            })?;
+
+            for row in rows {
+                let (uuid, freq, len) = row?;
+                let f = freq as f64;
+                let contribution = penalty * idf * (f * (k1 + 1.0))
+                    / (f + k1 * (1.0 - b + b * len as f64 / avgdl));
+                *scores.entry(uuid).or_insert(0.0) += contribution;
+            }
+        }
+    }
+
+    // resolve names and sort by descending score
+    let mut results: Vec<SearchResult> = Vec::new();
+    for (uuid, score) in scores {
+        let name: String = conn
+            .query_row("SELECT name FROM snip WHERE uuid = :uuid", &[(":uuid", &uuid)], |row| row.get(0))
+            .unwrap_or_default();
+        results.push(SearchResult { uuid, name, score });
+    }
+    results.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal));
+    Ok(results)
+}
+
+fn stem_something(s: &str, algo: Algorithm) -> String {
+    let stemmer = Stemmer::create(algo);
     stemmer.stem(s.to_lowercase().as_str()).to_string()
 }
 
+// map a language name to a rust_stemmers algorithm, falling back to English
+fn algorithm_from_lang(lang: &str) -> Algorithm {
+    match lang.to_lowercase().as_str() {
+        "arabic" => Algorithm::Arabic,
+        "danish" => Algorithm::Danish,
+        "dutch" => Algorithm::Dutch,
+        "english" => Algorithm::English,
+        "finnish" => Algorithm::Finnish,
+        "french" => Algorithm::French,
+        "german" => Algorithm::German,
+        "greek" => Algorithm::Greek,
+        "hungarian" => Algorithm::Hungarian,
+        "italian" => Algorithm::Italian,
+        "norwegian" => Algorithm::Norwegian,
+        "portuguese" => Algorithm::Portuguese,
+        "romanian" => Algorithm::Romanian,
+        "russian" => Algorithm::Russian,
+        "spanish" => Algorithm::Spanish,
+        "swedish" => Algorithm::Swedish,
+        "tamil" => Algorithm::Tamil,
+        "turkish" => Algorithm::Turkish,
+        _ => Algorithm::English,
+    }
+}
+
 fn read_data_from_stdin() -> Result<String, io::Error> {
     let mut buffer = String::new();
     io::stdin().read_line(&mut buffer)?;
@@ -240,4 +669,89 @@ that was an [empty] line.
         assert_eq!(expect, split);
         Ok(())
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn edit_distance_cutoff() {
+        // within the cutoff returns the exact distance
+        assert_eq!(edit_distance_within("consectetr", "consectetur", 2), Some(1));
+        assert_eq!(edit_distance_within("kitten", "sitting", 3), Some(3));
+        // identical strings have distance zero
+        assert_eq!(edit_distance_within("amet", "amet", 1), Some(0));
+        // beyond the cutoff abandons the candidate
+        assert_eq!(edit_distance_within("kitten", "sitting", 2), None);
+        // a length gap larger than the cutoff is rejected up front
+        assert_eq!(edit_distance_within("a", "abcd", 2), None);
+    }
+
+    fn index_test_db() -> Result<Connection, Box<dyn Error>> {
+        let conn = Connection::open_in_memory()?;
+        conn.execute("CREATE TABLE snip(uuid TEXT, name TEXT, timestamp TEXT, data TEXT, lang TEXT)", [])?;
+        conn.execute(
+            "CREATE TABLE snip_attachment(uuid TEXT, snip_uuid TEXT, timestamp TEXT, name TEXT, data BLOB, size INTEGER, content_type TEXT)",
+            [],
+        )?;
+        let ts = "2023-01-01T00:00:00+00:00";
+        let docs = [
+            ("11111111-1111-1111-1111-111111111111", "a", "lorem ipsum dolor"),
+            ("22222222-2222-2222-2222-222222222222", "b", "lorem ipsum sit amet consectetur"),
+            ("33333333-3333-3333-3333-333333333333", "c", "something entirely different here"),
+        ];
+        for (uuid, name, text) in docs {
+            conn.execute(
+                "INSERT INTO snip(uuid, name, timestamp, data, lang) VALUES(:uuid, :name, :ts, :data, NULL)",
+                &[(":uuid", uuid), (":name", name), (":ts", ts), (":data", text)],
+            )?;
+            let snip = Snip {
+                uuid: uuid.to_string(),
+                name: name.to_string(),
+                text: text.to_string(),
+                timestamp: DateTime::parse_from_rfc3339(ts)?,
+                lang: None,
+            };
+            index_snip(&conn, &snip, Algorithm::English)?;
+        }
+        Ok(conn)
+    }
+
+    #[test]
+    fn bm25_ranks_by_relevance() -> Result<(), Box<dyn Error>> {
+        let conn = index_test_db()?;
+        let results = search_snips(&conn, "dolor", false, BM25_K1, BM25_B, None, Algorithm::English)?;
+        // only the snip mentioning "dolor" matches, and it scores positively
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].name, "a");
+        assert!(results[0].score > 0.0);
+        Ok(())
+    }
+
+    #[test]
+    fn bm25_orders_shorter_document_first() -> Result<(), Box<dyn Error>> {
+        let conn = index_test_db()?;
+        // both "a" and "b" contain "lorem"; "a" is shorter, so it should rank first
+        let results = search_snips(&conn, "lorem", false, BM25_K1, BM25_B, None, Algorithm::English)?;
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].name, "a");
+        Ok(())
+    }
+
+    #[test]
+    fn config_defaults_and_precedence() -> Result<(), Box<dyn Error>> {
+        // defaults mirror the built-in tuning constants
+        let default = Config::default();
+        assert_eq!(default.lang, "english");
+        assert_eq!(default.bm25_k1, BM25_K1);
+        assert_eq!(default.bm25_b, BM25_B);
+        assert!(default.db_path.is_none());
+
+        // a partial file overrides only the fields it names, leaving the rest at default
+        let dir = env::temp_dir();
+        let path = dir.join("snip-rs-test-config.toml");
+        std::fs::write(&path, "lang = \"french\"\ndb_path = \"/tmp/snips.db\"\n")?;
+        let config = Config::from_file(path.to_str().unwrap())?;
+        assert_eq!(config.lang, "french");
+        assert_eq!(config.db_path.as_deref(), Some("/tmp/snips.db"));
+        assert_eq!(config.bm25_k1, BM25_K1);
+        std::fs::remove_file(&path)?;
+        Ok(())
+    }
+}
@@ -15,6 +15,72 @@ pub struct Attachment {
     pub name: String,
     pub data: Vec<u8>,
     pub size: usize,
+    pub content_type: AttachmentType,
+}
+
+/// AttachmentType classifies attachment content after the fashion of a MIME type
+pub enum AttachmentType {
+    /// Human-readable text, eligible for the full-text index
+    Text,
+    /// Opaque binary data carrying a short format tag (e.g. "pdf", "png")
+    Data { tag: String },
+    /// Container made of several parts; the variant carries no part data
+    Multipart,
+}
+
+impl AttachmentType {
+    /// Classify data by sniffing leading magic bytes, falling back to the filename extension
+    pub fn classify(data: &[u8], name: &str) -> AttachmentType {
+        // recognised file signatures
+        if data.starts_with(b"%PDF-") {
+            return AttachmentType::Data { tag: "pdf".to_string() };
+        }
+        if data.starts_with(b"PK\x03\x04") {
+            return AttachmentType::Data { tag: "zip".to_string() };
+        }
+        if data.starts_with(b"\x89PNG\r\n\x1a\n") {
+            return AttachmentType::Data { tag: "png".to_string() };
+        }
+        if data.starts_with(&[0xff, 0xd8, 0xff]) {
+            return AttachmentType::Data { tag: "jpeg".to_string() };
+        }
+
+        // fall back to the filename extension
+        if let Some(ext) = Path::new(name).extension().and_then(|e| e.to_str()) {
+            return match ext.to_lowercase().as_str() {
+                "txt" | "md" | "text" => AttachmentType::Text,
+                other => AttachmentType::Data { tag: other.to_string() },
+            };
+        }
+
+        // with no signature or extension, treat valid UTF-8 as text
+        if std::str::from_utf8(data).is_ok() {
+            AttachmentType::Text
+        } else {
+            AttachmentType::Data { tag: "bin".to_string() }
+        }
+    }
+
+    /// Render the type for storage in the content_type column
+    pub fn as_column(&self) -> String {
+        match self {
+            AttachmentType::Text => "text".to_string(),
+            AttachmentType::Data { tag } => format!("data/{}", tag),
+            AttachmentType::Multipart => "multipart".to_string(),
+        }
+    }
+
+    /// Parse a type from its stored content_type column value
+    pub fn from_column(s: &str) -> AttachmentType {
+        match s {
+            "text" => AttachmentType::Text,
+            "multipart" => AttachmentType::Multipart,
+            other => match other.strip_prefix("data/") {
+                Some(tag) => AttachmentType::Data { tag: tag.to_string() },
+                None => AttachmentType::Data { tag: other.to_string() },
+            },
+        }
+    }
 }
 
 impl Attachment {
@@ -27,6 +93,36 @@ impl Attachment {
         }
         Ok(())
     }
+
+    /// Return the decoded text of a text-typed attachment for full-text indexing
+    pub fn text_content(&self) -> Option<String> {
+        match self.content_type {
+            AttachmentType::Text => String::from_utf8(self.data.clone()).ok(),
+            _ => None,
+        }
+    }
+}
+
+/// Add the content_type column to pre-existing snip_attachment tables; the ALTER
+/// fails harmlessly once the column is already present
+pub fn ensure_content_type_column(conn: &Connection) {
+    let _ = conn.execute("ALTER TABLE snip_attachment ADD COLUMN content_type TEXT", []);
+}
+
+/// Return the text of every Text-typed attachment on a snip, for full-text indexing
+pub fn text_attachments_for_snip(conn: &Connection, snip_uuid: &str) -> Result<Vec<String>, Box<dyn Error>> {
+    let mut stmt = conn.prepare("SELECT uuid FROM snip_attachment WHERE snip_uuid = :snip_uuid")?;
+    let rows = stmt.query_map(&[(":snip_uuid", &snip_uuid)], |row| row.get::<_, String>(0))?;
+
+    let mut texts: Vec<String> = Vec::new();
+    for id in rows {
+        let uuid = Uuid::try_parse(id?.as_str())?;
+        let a = get_attachment_from_uuid(conn, uuid)?;
+        if let Some(text) = a.text_content() {
+            texts.push(text);
+        }
+    }
+    Ok(texts)
 }
 
 /// Returns an Attachment struct parsed from the database
@@ -45,11 +141,13 @@ fn attachment_from_db(
     timestamp: String,
     name: String,
     size: usize,
+    content_type: String,
     data: Vec<u8>,
 ) -> Result<Attachment, Box<dyn Error>> {
     let uuid = Uuid::try_parse(uuid.as_str())?;
     let snip_uuid = Uuid::try_parse(snip_uuid.as_str())?;
     let timestamp = DateTime::parse_from_rfc3339(timestamp.as_str())?;
+    let content_type = AttachmentType::from_column(content_type.as_str());
 
     Ok(Attachment {
         uuid,
@@ -57,6 +155,7 @@ fn attachment_from_db(
         timestamp,
         name,
         size,
+        content_type,
         data,
     })
 }
@@ -71,6 +170,9 @@ pub fn add_attachment(conn: &Connection, snip_uuid: Uuid, path: &Path) -> Result
     let data = std::fs::read(path)?;
     let size = data.len();
 
+    // classify by sniffing magic bytes, falling back to the extension
+    let content_type = AttachmentType::classify(&data, &name);
+
     // assign new Attachment
     let a = Attachment {
         uuid,
@@ -79,16 +181,18 @@ pub fn add_attachment(conn: &Connection, snip_uuid: Uuid, path: &Path) -> Result
         name,
         data,
         size,
+        content_type,
     };
 
     // insert
-    let mut stmt = conn.prepare("INSERT INTO snip_attachment(uuid, snip_uuid, timestamp, name, data, size) VALUES(:uuid, :snip_uuid, :timestamp, :name, ZEROBLOB(:size), :size)")?;
+    let mut stmt = conn.prepare("INSERT INTO snip_attachment(uuid, snip_uuid, timestamp, name, data, size, content_type) VALUES(:uuid, :snip_uuid, :timestamp, :name, ZEROBLOB(:size), :size, :content_type)")?;
     let result = stmt.execute(&[
         (":uuid", &a.uuid.to_string()),
         (":snip_uuid", &a.snip_uuid.to_string()),
         (":timestamp", &a.timestamp.to_rfc3339().to_string()),
         (":name", &a.name.to_string()),
         (":size", &a.size.to_string()),
+        (":content_type", &a.content_type.as_column()),
     ])?;
     assert_eq!(result, 1);
 
@@ -103,12 +207,12 @@ pub fn add_attachment(conn: &Connection, snip_uuid: Uuid, path: &Path) -> Result
 pub fn get_attachment_from_uuid(conn: &Connection, id: Uuid) -> Result<Attachment, Box<dyn Error>> {
     // get metadata
     let mut stmt = conn
-        .prepare("SELECT uuid, snip_uuid, timestamp, name, size, rowid FROM snip_attachment WHERE uuid = :id")?;
+        .prepare("SELECT uuid, snip_uuid, timestamp, name, size, content_type, rowid FROM snip_attachment WHERE uuid = :id")?;
     let mut rows = stmt.query_and_then(&[(":id", &id.to_string())], |row| {
         // read data first using rowid
-        let row_id: i64 = row.get(5)?;
+        let row_id: i64 = row.get(6)?;
         let data = attachment_data_from_db(conn, row_id)?;
-        attachment_from_db(row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?, data)
+        attachment_from_db(row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?, row.get(5)?, data)
     })?;
 
     if let Some(a) = rows.next() {
@@ -175,6 +279,23 @@ mod test {
     use crate::snip::test_prep::*;
     use crate::snip::SnipError;
 
+    #[test]
+    fn test_classify() {
+        // magic bytes take precedence over the filename
+        assert_eq!(AttachmentType::classify(b"%PDF-1.7", "note.txt").as_column(), "data/pdf");
+        assert_eq!(AttachmentType::classify(b"PK\x03\x04", "a.bin").as_column(), "data/zip");
+        assert_eq!(AttachmentType::classify(b"\x89PNG\r\n\x1a\n", "x").as_column(), "data/png");
+        assert_eq!(AttachmentType::classify(&[0xff, 0xd8, 0xff], "x").as_column(), "data/jpeg");
+
+        // extension fallback when no signature matches
+        assert_eq!(AttachmentType::classify(b"hello", "readme.md").as_column(), "text");
+        assert_eq!(AttachmentType::classify(b"hello", "archive.tar").as_column(), "data/tar");
+
+        // no signature or extension: valid UTF-8 is text, binary is data
+        assert_eq!(AttachmentType::classify(b"plain text", "noext").as_column(), "text");
+        assert_eq!(AttachmentType::classify(&[0x00, 0xff, 0x00], "noext").as_column(), "data/bin");
+    }
+
     #[test]
     fn test_add_attachment() -> Result<(), Box<dyn Error>> {
         let conn = prepare_database().expect("preparing in-memory database");
@@ -0,0 +1,80 @@
+use std::error::Error;
+use std::fmt;
+
+pub mod attachment;
+
+/// SnipError enumerates the error conditions raised across the snip module
+#[derive(Debug)]
+pub enum SnipError {
+    /// A generic failure carrying a human-readable message
+    General(String),
+    /// A supplied uuid (or partial) matched no rows
+    UuidNotFound(String),
+    /// A partial uuid matched more than one row
+    UuidMultipleMatches(String),
+}
+
+impl fmt::Display for SnipError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SnipError::General(s) => write!(f, "{}", s),
+            SnipError::UuidNotFound(s) => write!(f, "{}", s),
+            SnipError::UuidMultipleMatches(s) => write!(f, "{}", s),
+        }
+    }
+}
+
+impl Error for SnipError {}
+
+#[cfg(test)]
+pub mod test_prep {
+    use rusqlite::{Connection, DatabaseName};
+    use std::error::Error;
+
+    /// Uuid of the snip seeded by prepare_database
+    pub const ID_STR: &str = "ba652e2d-b248-4bcc-b36e-c26c0d3e8f2e";
+    /// Uuid of the attachment seeded by prepare_database
+    pub const ID_ATTACH_STR: &str = "9cfc5a2d-2946-48ee-82e0-227ba4bcdbd5";
+
+    /// Build an in-memory database seeded with one snip and one text attachment
+    pub fn prepare_database() -> Result<Connection, Box<dyn Error>> {
+        let conn = Connection::open_in_memory()?;
+        conn.execute(
+            "CREATE TABLE snip(uuid TEXT, name TEXT, timestamp TEXT, data TEXT, lang TEXT)",
+            [],
+        )?;
+        conn.execute(
+            "CREATE TABLE snip_attachment(uuid TEXT, snip_uuid TEXT, timestamp TEXT, name TEXT, data BLOB, size INTEGER, content_type TEXT)",
+            [],
+        )?;
+
+        let timestamp = "2023-01-01T00:00:00+00:00";
+        conn.execute(
+            "INSERT INTO snip(uuid, name, timestamp, data, lang) VALUES(:uuid, :name, :timestamp, :data, NULL)",
+            &[
+                (":uuid", ID_STR),
+                (":name", "test snip"),
+                (":timestamp", timestamp),
+                (":data", "lorem ipsum dolor sit amet"),
+            ],
+        )?;
+
+        let data = b"attachment text content";
+        conn.execute(
+            "INSERT INTO snip_attachment(uuid, snip_uuid, timestamp, name, data, size, content_type) VALUES(:uuid, :snip_uuid, :timestamp, :name, ZEROBLOB(:size), :size, :content_type)",
+            &[
+                (":uuid", ID_ATTACH_STR),
+                (":snip_uuid", ID_STR),
+                (":timestamp", timestamp),
+                (":name", "note.txt"),
+                (":size", &data.len().to_string()),
+                (":content_type", "text"),
+            ],
+        )?;
+        let row_id = conn.last_insert_rowid();
+        let mut blob = conn.blob_open(DatabaseName::Main, "snip_attachment", "data", row_id, false)?;
+        blob.write_at(data, 0)?;
+
+        Ok(conn)
+    }
+}